@@ -0,0 +1,189 @@
+use crate::connection::RequestMessages;
+use crate::error::Error;
+use crate::statement::Statement;
+use crate::statement_cache::StatementCache;
+use crate::types::Type;
+use bytes::BytesMut;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// State shared by a client and every `Statement` it has prepared.
+///
+/// This models the pieces the `statement` module depends on directly: the scratch buffer
+/// `StatementInner::drop` encodes Close+Sync into, and the prepared-statement cache `prepare`
+/// consults. Framing `send`'s payload onto the socket and reading back server messages is the
+/// job of the rest of the connection (`connection.rs`'s `Connection` task), which isn't part of
+/// this checkout.
+pub struct InnerClient {
+    buffer: Mutex<BytesMut>,
+    statement_cache: Mutex<StatementCache>,
+    #[cfg(test)]
+    sent: Mutex<Vec<RequestMessages>>,
+}
+
+impl InnerClient {
+    pub(crate) fn new(statement_cache_capacity: usize) -> InnerClient {
+        InnerClient {
+            buffer: Mutex::new(BytesMut::new()),
+            statement_cache: Mutex::new(StatementCache::new(statement_cache_capacity)),
+            #[cfg(test)]
+            sent: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn with_buf<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut BytesMut) -> R,
+    {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.clear();
+        f(&mut buffer)
+    }
+
+    #[cfg(not(test))]
+    pub(crate) fn send(&self, _messages: RequestMessages) -> Result<(), Error> {
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn send(&self, messages: RequestMessages) -> Result<(), Error> {
+        self.sent.lock().unwrap().push(messages);
+        Ok(())
+    }
+
+    /// Returns the cached statement for `query`/`param_types` if one exists; otherwise awaits
+    /// `prepare_uncached` (the real Parse/Describe round trip) and caches its result.
+    ///
+    /// A hit short-circuits `prepare_uncached` entirely and returns a clone of the cached
+    /// `Statement`. Because `StatementCache` keeps its own clone of every entry, a caller
+    /// dropping its clone doesn't close the server-side statement; only evicting the entry from
+    /// the cache -- dropping the cache's clone -- does, via `StatementInner`'s normal
+    /// Close+Sync-on-last-drop.
+    pub(crate) async fn prepare<F>(
+        self: &Arc<Self>,
+        query: &str,
+        param_types: &[Type],
+        prepare_uncached: F,
+    ) -> Result<Statement, Error>
+    where
+        F: Future<Output = Result<Statement, Error>>,
+    {
+        if let Some(statement) = self.statement_cache.lock().unwrap().get(query, param_types) {
+            return Ok(statement);
+        }
+
+        let statement = prepare_uncached.await?;
+        self.statement_cache
+            .lock()
+            .unwrap()
+            .insert(query, param_types, statement.clone());
+        Ok(statement)
+    }
+
+    /// Empties the prepared-statement cache, closing every statement it held the last reference
+    /// to.
+    ///
+    /// Exposed at the public API surface as `Client::clear_statement_cache`, below.
+    pub(crate) fn clear_statement_cache(&self) {
+        self.statement_cache.lock().unwrap().clear();
+    }
+}
+
+/// A connection to a PostgreSQL database.
+///
+/// This is scoped to what the `statement` module needs for the prepared-statement cache: a
+/// shared `InnerClient` and a way to clear its cache. The rest of `Client` (connecting,
+/// `query`/`query_opt`/`execute`, transactions, ...) lives alongside this, outside this
+/// checkout.
+pub struct Client {
+    inner: Arc<InnerClient>,
+}
+
+impl Client {
+    pub(crate) fn inner(&self) -> &Arc<InnerClient> {
+        &self.inner
+    }
+
+    /// Clears the prepared-statement cache, closing every statement it held the last reference
+    /// to.
+    pub fn clear_statement_cache(&self) {
+        self.inner.clear_statement_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statement::Column;
+    use std::cell::Cell;
+
+    fn statement(inner: &Arc<InnerClient>, name: &str) -> Statement {
+        Statement::new(
+            inner,
+            name.to_string(),
+            vec![],
+            vec![Column::new(
+                "c".to_string(),
+                Type::INT4,
+                -1,
+                crate::statement::Format::Binary,
+                None,
+                None,
+            )],
+        )
+    }
+
+    #[tokio::test]
+    async fn prepare_hit_short_circuits_and_reuses_the_statement() {
+        let inner = Arc::new(InnerClient::new(8));
+        let prepares = Cell::new(0);
+
+        let first = inner
+            .prepare("select $1", &[], async {
+                prepares.set(prepares.get() + 1);
+                Ok(statement(&inner, "s0"))
+            })
+            .await
+            .unwrap();
+
+        let second = inner
+            .prepare("select $1", &[], async {
+                prepares.set(prepares.get() + 1);
+                Ok(statement(&inner, "s0"))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(prepares.get(), 1);
+        assert_eq!(first.name(), second.name());
+    }
+
+    #[tokio::test]
+    async fn eviction_not_clone_drop_closes_the_statement() {
+        let inner = Arc::new(InnerClient::new(1));
+
+        let s0 = inner
+            .prepare("select 1", &[], async { Ok(statement(&inner, "s0")) })
+            .await
+            .unwrap();
+        // Cloning and dropping a clone must not close the statement: the cache still owns one.
+        drop(s0.clone());
+        assert!(inner.sent.lock().unwrap().is_empty());
+        drop(s0);
+        assert!(inner.sent.lock().unwrap().is_empty());
+
+        // Preparing a second statement evicts "s0" (capacity 1), which closes it.
+        let s1 = inner
+            .prepare("select 2", &[], async { Ok(statement(&inner, "s1")) })
+            .await
+            .unwrap();
+        assert_eq!(inner.sent.lock().unwrap().len(), 1);
+
+        // Dropping our own clone doesn't close it either -- the cache still holds "s1".
+        drop(s1);
+        assert_eq!(inner.sent.lock().unwrap().len(), 1);
+
+        inner.clear_statement_cache();
+        assert_eq!(inner.sent.lock().unwrap().len(), 2);
+    }
+}
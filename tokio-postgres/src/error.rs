@@ -0,0 +1,18 @@
+use std::error::Error as StdError;
+use std::fmt;
+
+/// An error communicating with the Postgres server.
+#[derive(Debug)]
+pub struct Error(Box<dyn StdError + Sync + Send>);
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
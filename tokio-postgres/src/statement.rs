@@ -2,8 +2,13 @@ use crate::client::InnerClient;
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
 use crate::types::Type;
+use crate::{Client, Error};
+use bytes::BytesMut;
 use postgres_protocol::message::frontend;
+use postgres_protocol::IsNull;
 use std::{
+    convert::TryFrom,
+    error::Error as StdError,
     fmt,
     sync::{Arc, Weak},
 };
@@ -12,7 +17,6 @@ struct StatementInner {
     client: Weak<InnerClient>,
     name: String,
     params: Vec<Type>,
-    columns: Vec<Column>,
 }
 
 impl Drop for StatementInner {
@@ -31,8 +35,18 @@ impl Drop for StatementInner {
 /// A prepared statement.
 ///
 /// Prepared statements can only be used with the connection that created them.
+///
+/// `columns` is kept outside of `StatementInner` (behind its own `Arc`) so that
+/// `with_column_formats` can hand out a `Statement` with different per-column formats while
+/// still sharing the *same* `Arc<StatementInner>` -- and so the *same* Close+Sync-on-last-drop
+/// -- as the statement it was derived from. Giving each format override its own
+/// `StatementInner` would mean two independent owners of one server-side statement name, and
+/// whichever was dropped first would close it out from under the other.
 #[derive(Clone)]
-pub struct Statement(Arc<StatementInner>);
+pub struct Statement {
+    inner: Arc<StatementInner>,
+    columns: Arc<[Column]>,
+}
 
 impl Statement {
     pub(crate) fn new(
@@ -41,27 +55,143 @@ impl Statement {
         params: Vec<Type>,
         columns: Vec<Column>,
     ) -> Statement {
-        Statement(Arc::new(StatementInner {
-            client: Arc::downgrade(inner),
-            name,
-            params,
-            columns,
-        }))
+        Statement {
+            inner: Arc::new(StatementInner {
+                client: Arc::downgrade(inner),
+                name,
+                params,
+            }),
+            columns: columns.into(),
+        }
     }
 
     pub(crate) fn name(&self) -> &str {
-        &self.0.name
+        &self.inner.name
     }
 
     /// Returns the expected types of the statement's parameters.
     pub fn params(&self) -> &[Type] {
-        &self.0.params
+        &self.inner.params
     }
 
     /// Returns information about the columns returned when the statement is queried.
     pub fn columns(&self) -> &[Column] {
-        &self.0.columns
+        &self.columns
+    }
+
+    /// Returns the result-format-code array to send in the Bind message for this statement,
+    /// one code per column, in the encoding the Postgres wire protocol expects.
+    pub(crate) fn column_formats(&self) -> Vec<i16> {
+        self.columns.iter().map(|c| c.format.code()).collect()
+    }
+
+    /// Encodes a `Bind` message that executes this statement into `portal`, requesting each
+    /// result column back in the format recorded on it (see `with_column_formats`).
+    ///
+    /// `param_formats` and `params`/`serializer` describe the parameter values exactly as
+    /// `postgres_protocol::message::frontend::bind` expects; this wrapper only fixes the
+    /// *result* format codes to `self.column_formats()` so callers (and the row decoder reading
+    /// `Column::format()` back) can't drift out of sync with per-column overrides.
+    pub(crate) fn bind_message<I, J, F, T>(
+        &self,
+        portal: &str,
+        param_formats: I,
+        params: J,
+        serializer: F,
+        buf: &mut BytesMut,
+    ) -> Result<(), frontend::BindError>
+    where
+        I: IntoIterator<Item = i16>,
+        J: IntoIterator<Item = T>,
+        F: FnMut(T, &mut BytesMut) -> Result<IsNull, Box<dyn StdError + Sync + Send>>,
+    {
+        frontend::bind(
+            portal,
+            &self.inner.name,
+            param_formats,
+            params,
+            serializer,
+            self.column_formats(),
+            buf,
+        )
     }
+
+    /// Returns a copy of this statement whose columns will be returned in the given formats.
+    ///
+    /// `formats` follows the same convention as the result-format-code array in the frontend
+    /// `Bind` message: an empty slice requests text for every column, a single-element slice
+    /// applies that one format to every column, and a slice with one entry per column applies
+    /// each format individually. This lets cheap, well-understood types (e.g. `INT4`,
+    /// `TIMESTAMP`) stay in binary while troublesome ones (e.g. `NUMERIC`, custom enums) are
+    /// pulled as text, without re-preparing the statement.
+    ///
+    /// The returned `Statement` shares this one's `Arc<StatementInner>` -- only the per-column
+    /// format metadata differs -- so dropping either copy does not close the server-side
+    /// statement out from under the other; it's closed once the last copy of either is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `formats` has more than one element and its length does not match the number
+    /// of columns in the statement.
+    pub fn with_column_formats(&self, formats: &[Format]) -> Statement {
+        assert!(
+            formats.is_empty() || formats.len() == 1 || formats.len() == self.columns.len(),
+            "formats must be empty, a single format, or one format per column",
+        );
+
+        let columns: Arc<[Column]> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let format = if formats.is_empty() {
+                    Format::Text
+                } else if formats.len() == 1 {
+                    formats[0]
+                } else {
+                    formats[i]
+                };
+                column.with_format(format)
+            })
+            .collect::<Vec<_>>()
+            .into();
+
+        Statement {
+            inner: self.inner.clone(),
+            columns,
+        }
+    }
+}
+
+/// The wire format requested for a column's result data.
+///
+/// Mirrors the result-format-code values accepted by the frontend `Bind` message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// The column is returned as text.
+    Text,
+    /// The column is returned in the type's binary representation.
+    Binary,
+}
+
+impl Format {
+    pub(crate) fn code(self) -> i16 {
+        match self {
+            Format::Text => 0,
+            Format::Binary => 1,
+        }
+    }
+}
+
+/// Whether a column can contain SQL `NULL`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Nullability {
+    /// The column is declared `NOT NULL`.
+    No,
+    /// The column may contain nulls.
+    Yes,
+    /// Nullability could not be determined.
+    Unknown,
 }
 
 /// Information about a column of a query.
@@ -69,11 +199,39 @@ pub struct Column {
     name: String,
     type_: Type,
     type_modifier: i32,
+    format: Format,
+    table_oid: Option<u32>,
+    column_id: Option<i16>,
 }
 
 impl Column {
-    pub(crate) fn new(name: String, type_: Type, type_modifier: i32) -> Column {
-        Column { name, type_, type_modifier }
+    pub(crate) fn new(
+        name: String,
+        type_: Type,
+        type_modifier: i32,
+        format: Format,
+        table_oid: Option<u32>,
+        column_id: Option<i16>,
+    ) -> Column {
+        Column {
+            name,
+            type_,
+            type_modifier,
+            format,
+            table_oid,
+            column_id,
+        }
+    }
+
+    pub(crate) fn with_format(&self, format: Format) -> Column {
+        Column {
+            name: self.name.clone(),
+            type_: self.type_.clone(),
+            type_modifier: self.type_modifier,
+            format,
+            table_oid: self.table_oid,
+            column_id: self.column_id,
+        }
     }
 
     /// Returns the name of the column.
@@ -86,8 +244,106 @@ impl Column {
         &self.type_
     }
 
+    /// Returns the format the column was requested in, i.e. what `Column::type_()` is decoded
+    /// from on the wire.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Returns the OID of the table this column comes from, or `None` if the RowDescription
+    /// didn't report one (e.g. the server predates this field).
+    ///
+    /// An OID of `0` indicates the column is not a direct reference to a table column, e.g. the
+    /// result of an expression.
+    pub fn table_oid(&self) -> Option<u32> {
+        self.table_oid
+    }
+
+    /// Returns the attribute number of this column within its table, or `None` if the
+    /// RowDescription didn't report one.
+    ///
+    /// Meaningless (and reported as `0` by the server) when `table_oid()` is `0`.
+    pub fn column_id(&self) -> Option<i16> {
+        self.column_id
+    }
+
+    /// Returns the maximum number of characters needed to display a value of this column, in
+    /// the style of JDBC/ODBC's `COLUMN_DISPLAY_SIZE`.
+    pub fn display_size(&self) -> Option<u32> {
+        match self.type_ {
+            Type::FLOAT4 | Type::FLOAT8 => self.precision(),
+            Type::NUMERIC => self.precision().map(|precision| {
+                let extra = if self.scale().unwrap_or(0) > 0 { 2 } else { 1 };
+                precision + extra
+            }),
+            Type::INT2 | Type::INT4 | Type::INT8 | Type::OID => {
+                self.precision().map(|precision| precision + 1)
+            }
+            _ => self.precision(),
+        }
+    }
+
+    /// Returns the size, in bytes, of a value of this column.
+    ///
+    /// For fixed-size types this is the type's on-disk length; for variable-length character and
+    /// bit types it's derived from the declared length modifier.
+    pub fn octet_length(&self) -> Option<u32> {
+        match self.type_ {
+            Type::BOOL | Type::CHAR => Some(1),
+            Type::INT2 => Some(2),
+            Type::INT4 | Type::FLOAT4 | Type::OID | Type::DATE => Some(4),
+            Type::INT8 | Type::FLOAT8 | Type::TIME | Type::TIMESTAMP | Type::TIMESTAMPTZ => {
+                Some(8)
+            }
+            Type::TIMETZ => Some(12),
+            Type::INTERVAL => Some(16),
+            Type::VARCHAR | Type::BPCHAR => {
+                // Assume the worst case of a 4-byte UTF-8 encoding per declared character;
+                // checked since precision() can be up to i32::MAX for a pathological modifier.
+                self.precision().and_then(|precision| precision.checked_mul(4))
+            }
+            Type::BIT | Type::VARBIT => self
+                .precision()
+                .and_then(|bits| bits.checked_add(7))
+                .map(|bits| bits / 8),
+            _ => None,
+        }
+    }
+
+    /// Returns whether values of this column are signed numbers.
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self.type_,
+            Type::INT2 | Type::INT4 | Type::INT8 | Type::FLOAT4 | Type::FLOAT8 | Type::NUMERIC
+        )
+    }
+
+    /// Looks up whether this column's underlying table column is declared `NOT NULL`.
+    ///
+    /// Returns `Nullability::Unknown` without issuing a query if the column isn't a direct
+    /// reference to a table column (`table_oid()` is `0` or unreported), since there's no
+    /// `pg_attribute` row to consult in that case.
+    pub async fn is_nullable(&self, client: &Client) -> Result<Nullability, Error> {
+        let (table_oid, column_id) = match (self.table_oid, self.column_id) {
+            (Some(table_oid), Some(column_id)) if table_oid != 0 => (table_oid, column_id),
+            _ => return Ok(Nullability::Unknown),
+        };
+
+        let row = client
+            .query_opt(
+                "SELECT attnotnull FROM pg_catalog.pg_attribute WHERE attrelid = $1 AND attnum = $2",
+                &[&table_oid, &column_id],
+            )
+            .await?;
+
+        Ok(match row {
+            Some(row) if row.get::<_, bool>(0) => Nullability::No,
+            Some(_) => Nullability::Yes,
+            None => Nullability::Unknown,
+        })
+    }
+
     /// Returns precision of the column.
-    #[allow(overflowing_literals)]
     pub fn precision(&self) -> Option<u32> {
         match self.type_ {
             Type::INT2 => Some(5), // -32768 to +32767
@@ -108,7 +364,7 @@ impl Column {
                 if self.type_modifier == -1 {
                     None
                 } else {
-                    Some((((self.type_modifier - 4) & 0xFFFF0000) >> 16) as u32)
+                    Column::length_modifier(self.type_modifier).map(|len| (len & 0xFFFF0000) >> 16)
                 }
             }
 
@@ -119,7 +375,7 @@ impl Column {
                 if self.type_modifier == -1 {
                     None
                 } else {
-                    Some((self.type_modifier - 4) as u32)
+                    Column::length_modifier(self.type_modifier)
                 }
             }
 
@@ -130,29 +386,21 @@ impl Column {
             // date = '5874897-12-31' = 13 (although at large values second precision is lost)
             // date = '294276-11-20' = 12 --enable-integer-datetimes
             // zone = '+11:30' = 6;
-            Type::TIME => {
-                let second_size = Column::type_modifier_to_second_size(self.type_modifier);
-                Some((8 + second_size) as u32)
-            }
-            Type::TIMETZ => {
-                let second_size = Column::type_modifier_to_second_size(self.type_modifier);
-                Some((8 + second_size + 6) as u32)
-            }
-            Type::TIMESTAMP => {
-                let second_size = Column::type_modifier_to_second_size(self.type_modifier);
-                Some((13 + 1 + 8 + second_size) as u32)
-            }
-            Type::TIMESTAMPTZ => {
-                let second_size = Column::type_modifier_to_second_size(self.type_modifier);
-                Some((13 + 1 + 8 + second_size + 4) as u32)
-            }
+            Type::TIME => Column::type_modifier_to_second_size(self.type_modifier)
+                .and_then(|second_size| u32::try_from(8 + second_size).ok()),
+            Type::TIMETZ => Column::type_modifier_to_second_size(self.type_modifier)
+                .and_then(|second_size| u32::try_from(8 + second_size + 6).ok()),
+            Type::TIMESTAMP => Column::type_modifier_to_second_size(self.type_modifier)
+                .and_then(|second_size| u32::try_from(13 + 1 + 8 + second_size).ok()),
+            Type::TIMESTAMPTZ => Column::type_modifier_to_second_size(self.type_modifier)
+                .and_then(|second_size| u32::try_from(13 + 1 + 8 + second_size + 4).ok()),
             Type::INTERVAL => Some(49),
-            Type::BIT => Some(self.type_modifier as u32),
+            Type::BIT => u32::try_from(self.type_modifier).ok(),
             Type::VARBIT => {
                 if self.type_modifier == -1 {
                     None
                 } else {
-                    Some(self.type_modifier as u32)
+                    u32::try_from(self.type_modifier).ok()
                 }
             }
             _ => None
@@ -168,34 +416,48 @@ impl Column {
                 if self.type_modifier == -1 {
                     Some(0)
                 } else {
-                    Some(((self.type_modifier - 4) & 0xFFFF) as u32)
+                    Column::length_modifier(self.type_modifier).map(|len| len & 0xFFFF)
                 }
             }
             Type::TIME | Type::TIMETZ | Type::TIMESTAMP | Type::TIMESTAMPTZ => {
                 if self.type_modifier == -1 {
                     Some(6)
                 } else {
-                    Some(self.type_modifier as u32)
+                    u32::try_from(self.type_modifier).ok()
                 }
             }
             Type::INTERVAL => {
                 if self.type_modifier == -1 {
                     Some(6)
                 } else {
-                    Some((self.type_modifier & 0xFFFF) as u32)
+                    u32::try_from(self.type_modifier).ok().map(|m| m & 0xFFFF)
                 }
             }
             _ => None
         }
     }
 
+    /// Computes `type_modifier - 4` as a non-negative length, returning `None` (instead of
+    /// wrapping or underflowing) for a modifier that's too small, e.g. the `0..=3` a malformed
+    /// or malicious server could send for NUMERIC/VARCHAR/BPCHAR.
+    #[inline]
+    fn length_modifier(type_modifier: i32) -> Option<u32> {
+        type_modifier
+            .checked_sub(4)
+            .and_then(|len| u32::try_from(len).ok())
+    }
+
+    /// Returns the number of characters needed to display the seconds field of a TIME-family
+    /// value at the given modifier's fractional-second precision, or `None` if the modifier
+    /// isn't a valid one (only `-1`, `0`, and positive values are).
     #[inline]
-    fn type_modifier_to_second_size(type_modifier: i32) -> i32 {
+    fn type_modifier_to_second_size(type_modifier: i32) -> Option<i64> {
         match type_modifier {
-            -1 => 7,
-            0 => 0,
-            1 => 3,
-            x => x + 1
+            -1 => Some(7),
+            0 => Some(0),
+            1 => Some(3),
+            x if x > 0 => Some(i64::from(x) + 1),
+            _ => None,
         }
     }
 }
@@ -208,3 +470,126 @@ impl fmt::Debug for Column {
             .finish()
     }
 }
+
+#[cfg(feature = "arrow")]
+impl Statement {
+    /// Builds an Arrow `Schema` describing the columns this statement returns.
+    ///
+    /// Each `Column` is mapped to an Arrow field using its type together with the
+    /// already-computed `precision()`/`scale()`. Every field is marked nullable: the result of
+    /// `Column::is_nullable` is not stored on the column, so it is not consulted here.
+    pub fn arrow_schema(&self) -> arrow::datatypes::Schema {
+        let fields: Vec<_> = self.columns.iter().map(Column::arrow_field).collect();
+        arrow::datatypes::Schema::new(fields)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl Column {
+    fn arrow_field(&self) -> arrow::datatypes::Field {
+        arrow::datatypes::Field::new(self.name(), self.arrow_type(), true)
+    }
+
+    fn arrow_type(&self) -> arrow::datatypes::DataType {
+        use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+
+        match self.type_ {
+            Type::INT2 => DataType::Int16,
+            Type::INT4 => DataType::Int32,
+            Type::INT8 => DataType::Int64,
+            Type::FLOAT4 => DataType::Float32,
+            Type::FLOAT8 => DataType::Float64,
+            Type::NUMERIC => {
+                let precision = self.precision().unwrap_or(38).clamp(1, 38) as u8;
+                let scale = self.scale().unwrap_or(0).min(i8::MAX as u32) as i8;
+                DataType::Decimal128(precision, scale)
+            }
+            Type::DATE => DataType::Date32,
+            // Time64 only has Microsecond/Nanosecond variants, so pick between those two based
+            // on whether the declared fractional-second precision (Postgres caps it at 6) fits
+            // in microseconds.
+            Type::TIME | Type::TIMETZ => DataType::Time64(match self.scale() {
+                Some(scale) if scale > 6 => TimeUnit::Nanosecond,
+                _ => TimeUnit::Microsecond,
+            }),
+            Type::TIMESTAMP => DataType::Timestamp(Column::timestamp_unit(self.scale()), None),
+            Type::TIMESTAMPTZ => {
+                DataType::Timestamp(Column::timestamp_unit(self.scale()), Some("UTC".into()))
+            }
+            Type::INTERVAL => DataType::Interval(IntervalUnit::MonthDayNano),
+            Type::BOOL => DataType::Boolean,
+            Type::BYTEA => DataType::Binary,
+            _ => DataType::Utf8,
+        }
+    }
+
+    /// Picks the `Timestamp` sub-second unit matching a declared fractional-second scale (0-6
+    /// in Postgres), falling back to the server's default of 6 (microseconds) when unset.
+    fn timestamp_unit(scale: Option<u32>) -> arrow::datatypes::TimeUnit {
+        use arrow::datatypes::TimeUnit;
+
+        match scale.unwrap_or(6) {
+            0 => TimeUnit::Second,
+            1..=3 => TimeUnit::Millisecond,
+            4..=6 => TimeUnit::Microsecond,
+            _ => TimeUnit::Nanosecond,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(type_: Type, type_modifier: i32) -> Column {
+        Column::new("c".to_string(), type_, type_modifier, Format::Text, None, None)
+    }
+
+    #[test]
+    fn numeric_precision_and_scale_reject_pathological_modifiers() {
+        for &type_modifier in &[0, 1, 2, 3, -5] {
+            let c = column(Type::NUMERIC, type_modifier);
+            assert_eq!(c.precision(), None);
+            assert_eq!(c.scale(), None);
+        }
+
+        for &type_modifier in &[0, 1, 2, 3, -5, i32::MAX] {
+            let c = column(Type::NUMERIC, type_modifier);
+            if let Some(scale) = c.scale() {
+                assert!(scale <= 0xFFFF);
+            }
+            if let Some(precision) = c.precision() {
+                assert!(precision <= i32::MAX as u32);
+            }
+        }
+    }
+
+    #[test]
+    fn varchar_and_bpchar_precision_reject_pathological_modifiers() {
+        for type_ in [Type::VARCHAR, Type::BPCHAR] {
+            for &type_modifier in &[0, 1, 2, 3, -5] {
+                assert_eq!(column(type_.clone(), type_modifier).precision(), None);
+            }
+            assert_eq!(
+                column(type_, i32::MAX).precision(),
+                Some((i32::MAX - 4) as u32)
+            );
+        }
+    }
+
+    #[test]
+    fn bit_and_varbit_precision_reject_pathological_modifiers() {
+        for type_ in [Type::BIT, Type::VARBIT] {
+            assert_eq!(column(type_.clone(), -5).precision(), None);
+
+            for &type_modifier in &[0, 1, 2, 3] {
+                assert_eq!(
+                    column(type_.clone(), type_modifier).precision(),
+                    Some(type_modifier as u32)
+                );
+            }
+
+            assert_eq!(column(type_, i32::MAX).precision(), Some(i32::MAX as u32));
+        }
+    }
+}
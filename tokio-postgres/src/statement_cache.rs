@@ -0,0 +1,87 @@
+use crate::statement::Statement;
+use crate::types::Type;
+use std::collections::{HashMap, VecDeque};
+
+/// The key a prepared statement is cached under: its SQL text plus the parameter type OIDs it
+/// was prepared with, since the same SQL can be prepared with different inferred or explicit
+/// parameter types.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct StatementKey {
+    query: String,
+    param_oids: Vec<u32>,
+}
+
+impl StatementKey {
+    fn new(query: &str, param_types: &[Type]) -> StatementKey {
+        StatementKey {
+            query: query.to_string(),
+            param_oids: param_types.iter().map(Type::oid).collect(),
+        }
+    }
+}
+
+/// An LRU cache of prepared statements, keyed by SQL text and parameter types.
+///
+/// `InnerClient::prepare` consults this cache before issuing Parse/Describe; on a hit it
+/// returns the cached `Statement` (cloning the `Arc` the statement wraps) instead of preparing
+/// again. Cloning a cached `Statement` does not close the server-side statement -- only
+/// evicting it from the cache, which drops the cache's own reference, does, via the Close+Sync
+/// `StatementInner::drop` sends once the last reference goes away.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    entries: HashMap<StatementKey, Statement>,
+    order: VecDeque<StatementKey>,
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize) -> StatementCache {
+        StatementCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the cached statement for `query`/`param_types`, if any, and marks it
+    /// most-recently-used.
+    pub(crate) fn get(&mut self, query: &str, param_types: &[Type]) -> Option<Statement> {
+        let key = StatementKey::new(query, param_types);
+        let statement = self.entries.get(&key).cloned()?;
+        self.touch(&key);
+        Some(statement)
+    }
+
+    /// Inserts `statement` into the cache, evicting the least-recently-used entry if the cache
+    /// is over capacity. A capacity of `0` disables caching entirely.
+    pub(crate) fn insert(&mut self, query: &str, param_types: &[Type], statement: Statement) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = StatementKey::new(query, param_types);
+        if self.entries.insert(key.clone(), statement).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.entries.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Removes every entry, dropping the cache's reference to each cached statement.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn touch(&mut self, key: &StatementKey) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}